@@ -0,0 +1,190 @@
+//! Pluggable swap curves, mirroring SPL token-swap's curve module: the invariant and the
+//! pool-token accounting used to mint/burn liquidity both live here instead of being baked
+//! into `swap`/`add_liquidity`/`remove_liquidty`.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::LiquidityPoolError;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TradeDirection {
+    AtoB,
+    BtoA,
+}
+
+// Shared `Constant*` prefix is intentional: it names the family of invariants this pool
+// supports, mirroring SPL token-swap's curve naming.
+#[allow(clippy::enum_variant_names)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SwapCurve {
+    /// The classic `x * y = k` invariant.
+    ConstantProduct,
+    /// A fixed exchange rate: `token_b_price` units of token B per unit of token A.
+    /// Meant for stable-ish 1:1 pegs, not for tokens that should float against each other.
+    ConstantPrice { token_b_price: u64 },
+    /// `x * (y + token_b_offset) = k`: token B trades against a virtual reserve so a pool
+    /// can launch with no initial B deposit.
+    ConstantProductWithOffset { token_b_offset: u64 },
+}
+
+// `new_token_a_reserve`/`new_token_b_reserve` round out the result for callers that want the
+// post-swap reserves directly instead of re-deriving them from `amount_out`; `swap`'s current
+// caller only needs `amount_out`.
+#[allow(dead_code)]
+pub struct SwapResult {
+    pub new_token_a_reserve: u128,
+    pub new_token_b_reserve: u128,
+    pub amount_out: u64,
+}
+
+impl SwapCurve {
+    /// Executes a swap of `source_amount` against the current `token_a_reserve`/`token_b_reserve`,
+    /// returning the post-swap reserves and the amount of the other token paid out.
+    pub fn swap(
+        &self,
+        source_amount: u128,
+        token_a_reserve: u128,
+        token_b_reserve: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapResult> {
+        match *self {
+            SwapCurve::ConstantProduct => {
+                let invariant = token_a_reserve.checked_mul(token_b_reserve)?;
+                // The new destination reserve rounds up (so `amount_out` rounds down), which keeps
+                // the product of the reserves from dropping below `invariant`.
+                match trade_direction {
+                    TradeDirection::AtoB => {
+                        let new_token_a_reserve = token_a_reserve.checked_add(source_amount)?;
+                        let new_token_b_reserve = invariant
+                            .checked_add(new_token_a_reserve.checked_sub(1)?)?
+                            .checked_div(new_token_a_reserve)?;
+                        let amount_out: u64 = token_b_reserve.checked_sub(new_token_b_reserve)?.try_into().ok()?;
+                        Some(SwapResult { new_token_a_reserve, new_token_b_reserve, amount_out })
+                    }
+                    TradeDirection::BtoA => {
+                        let new_token_b_reserve = token_b_reserve.checked_add(source_amount)?;
+                        let new_token_a_reserve = invariant
+                            .checked_add(new_token_b_reserve.checked_sub(1)?)?
+                            .checked_div(new_token_b_reserve)?;
+                        let amount_out: u64 = token_a_reserve.checked_sub(new_token_a_reserve)?.try_into().ok()?;
+                        Some(SwapResult { new_token_a_reserve, new_token_b_reserve, amount_out })
+                    }
+                }
+            }
+            SwapCurve::ConstantPrice { token_b_price } => {
+                let token_b_price = token_b_price as u128;
+                match trade_direction {
+                    TradeDirection::AtoB => {
+                        let amount_out = source_amount.checked_mul(token_b_price)?;
+                        let amount_out_u64: u64 = amount_out.try_into().ok()?;
+                        Some(SwapResult {
+                            new_token_a_reserve: token_a_reserve.checked_add(source_amount)?,
+                            new_token_b_reserve: token_b_reserve.checked_sub(amount_out)?,
+                            amount_out: amount_out_u64,
+                        })
+                    }
+                    TradeDirection::BtoA => {
+                        let amount_out = source_amount.checked_div(token_b_price)?;
+                        let amount_out_u64: u64 = amount_out.try_into().ok()?;
+                        Some(SwapResult {
+                            new_token_a_reserve: token_a_reserve.checked_sub(amount_out)?,
+                            new_token_b_reserve: token_b_reserve.checked_add(source_amount)?,
+                            amount_out: amount_out_u64,
+                        })
+                    }
+                }
+            }
+            SwapCurve::ConstantProductWithOffset { token_b_offset } => {
+                let effective_b_reserve = token_b_reserve.checked_add(token_b_offset as u128)?;
+                let invariant = token_a_reserve.checked_mul(effective_b_reserve)?;
+                match trade_direction {
+                    TradeDirection::AtoB => {
+                        let new_token_a_reserve = token_a_reserve.checked_add(source_amount)?;
+                        let new_effective_b_reserve = invariant.checked_div(new_token_a_reserve)?;
+                        let amount_out: u64 = effective_b_reserve.checked_sub(new_effective_b_reserve)?.try_into().ok()?;
+                        Some(SwapResult {
+                            new_token_a_reserve,
+                            new_token_b_reserve: new_effective_b_reserve.checked_sub(token_b_offset as u128)?,
+                            amount_out,
+                        })
+                    }
+                    TradeDirection::BtoA => {
+                        let new_effective_b_reserve = effective_b_reserve.checked_add(source_amount)?;
+                        let new_token_a_reserve = invariant.checked_div(new_effective_b_reserve)?;
+                        let amount_out: u64 = token_a_reserve.checked_sub(new_token_a_reserve)?.try_into().ok()?;
+                        Some(SwapResult {
+                            new_token_a_reserve,
+                            new_token_b_reserve: new_effective_b_reserve.checked_sub(token_b_offset as u128)?,
+                            amount_out,
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /// The curve's notion of "how much is this pool worth", used to size the very first
+    /// liquidity mint. One-sided deposits/withdrawals are priced in `lib.rs`, not here.
+    fn normalized_value(&self, token_a_amount: u128, token_b_amount: u128) -> Option<u128> {
+        match *self {
+            SwapCurve::ConstantProduct => token_a_amount.checked_mul(token_b_amount)?.isqrt_checked(),
+            SwapCurve::ConstantPrice { token_b_price } => {
+                token_a_amount.checked_mul(token_b_price as u128)?.checked_add(token_b_amount)
+            }
+            SwapCurve::ConstantProductWithOffset { token_b_offset } => token_a_amount
+                .checked_mul(token_b_amount.checked_add(token_b_offset as u128)?)?
+                .isqrt_checked(),
+        }
+    }
+
+    /// Pool tokens to mint for a balanced `(token_a_amount, token_b_amount)` deposit. Deposits
+    /// round down, so a depositor never receives more liquidity than their deposit is worth —
+    /// rounding up would let repeated balanced deposits dilute existing LPs' value-per-share.
+    pub fn deposit_liquidity_tokens(
+        &self,
+        token_a_amount: u128,
+        token_b_amount: u128,
+        token_a_reserve: u128,
+        token_b_reserve: u128,
+        liquidity_supply: u128,
+    ) -> Option<u128> {
+        if liquidity_supply == 0 {
+            return self.normalized_value(token_a_amount, token_b_amount);
+        }
+        let liquidity_a = token_a_amount.checked_mul(liquidity_supply)?.checked_div(token_a_reserve)?;
+        let liquidity_b = token_b_amount.checked_mul(liquidity_supply)?.checked_div(token_b_reserve)?;
+        Some(liquidity_a.min(liquidity_b))
+    }
+
+    /// Trading tokens returned for burning `liquidity_amount` pool tokens. Withdrawals round
+    /// down, so the pool never pays out more than its reserves can cover.
+    pub fn withdraw_trading_tokens(
+        &self,
+        liquidity_amount: u128,
+        liquidity_supply: u128,
+        token_a_reserve: u128,
+        token_b_reserve: u128,
+    ) -> Option<(u128, u128)> {
+        let amount_a = liquidity_amount.checked_mul(token_a_reserve)?.checked_div(liquidity_supply)?;
+        let amount_b = liquidity_amount.checked_mul(token_b_reserve)?.checked_div(liquidity_supply)?;
+        Some((amount_a, amount_b))
+    }
+
+    pub fn validate(&self) -> Result<(), LiquidityPoolError> {
+        match *self {
+            SwapCurve::ConstantPrice { token_b_price: 0 } => Err(LiquidityPoolError::InvalidCurve),
+            _ => Ok(()),
+        }
+    }
+}
+
+trait IsqrtChecked {
+    fn isqrt_checked(self) -> Option<u128>;
+}
+
+impl IsqrtChecked for u128 {
+    fn isqrt_checked(self) -> Option<u128> {
+        use num::integer::Roots;
+        Some(self.sqrt())
+    }
+}
@@ -1,26 +1,164 @@
+// `entrypoint!` expands to cfgs (`custom-heap`, `custom-panic`, target_os = "solana") this
+// crate's Cargo.toml never declares; that's solana-program's own default-entrypoint plumbing,
+// not something to silence by declaring fake features here.
+#![allow(unexpected_cfgs)]
+
 use num::integer::Roots;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo}, address_lookup_table::instruction, entrypoint::{self, entrypoint, ProgramResult}, msg, program::{invoke, invoke_signed}, program_error::ProgramError, pubkey::Pubkey
+    account_info::{next_account_info, AccountInfo}, entrypoint, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, pubkey::Pubkey
 };
 
 use borsh::{BorshSerialize, BorshDeserialize};
-use spl_token;
+use solana_program::program_option::COption;
 use solana_program::program_pack::Pack; // Import the Pack trait for unpacking accounts
 use spl_token::instruction as token_instruction;
 
+mod curve;
+// Only re-exported publicly under the `fuzz` feature, so the `fuzz/` harness can build the
+// curve values it passes to `process_instruction`.
+#[cfg(feature = "fuzz")]
+pub use curve::{SwapCurve, TradeDirection};
+#[cfg(not(feature = "fuzz"))]
+use curve::{SwapCurve, TradeDirection};
+
 // Your LiquidityPool struct (unchanged, but note typo fix: token_a_reserve, token_b_reserve)
 #[derive(BorshSerialize, BorshDeserialize)]
 struct LiquidityPool {
+    is_initialized: bool,
     authority: Pubkey,
+    bump_seed: u8,
     token_a_mint: Pubkey,
     token_b_mint: Pubkey,
     token_a_vault: Pubkey,
     token_b_vault: Pubkey,
     liquidity_mint: Pubkey,
+    fee_account: Pubkey,
+    fees: Fees,
+    swap_curve: SwapCurve,
     liquidity_supply: u64,
     token_a_reserve: u64, // Was token_a_reserve in your code
     token_b_reserve: u64, // Was token_b_reserve in your code
 }
+
+// Smallest a packed `LiquidityPool` (behind its `PoolVersion` byte) can legally be: every fixed
+// field plus the smallest `SwapCurve` encoding. Used to reject truncated/partially-written
+// accounts before `try_from_slice` gets anywhere near them.
+const POOL_MIN_LEN: usize = 1 // is_initialized
+    + 32 // authority
+    + 1 // bump_seed
+    + 32 * 5 // token_a_mint, token_b_mint, token_a_vault, token_b_vault, liquidity_mint
+    + 32 // fee_account
+    + 8 * 6 // fees
+    + 1 // swap_curve discriminant (ConstantProduct has no payload)
+    + 8 * 3; // liquidity_supply, token_a_reserve, token_b_reserve
+
+// Forward-compatible pool state, mirroring SPL token-swap's `SwapVersion`: the first byte on
+// the account is a version discriminant so a future layout change can't be silently
+// misinterpreted as today's `LiquidityPool`.
+enum PoolVersion {
+    V1(LiquidityPool),
+}
+
+impl PoolVersion {
+    const LATEST: u8 = 1;
+
+    fn pack(self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        if dst.len() < 1 + POOL_MIN_LEN {
+            return Err(LiquidityPoolError::InvalidAccount.into());
+        }
+        let PoolVersion::V1(pool) = self;
+        dst[0] = Self::LATEST;
+        pool.serialize(&mut &mut dst[1..])?;
+        Ok(())
+    }
+
+    fn unpack(src: &[u8]) -> Result<PoolVersion, ProgramError> {
+        if src.len() < 1 + POOL_MIN_LEN {
+            return Err(LiquidityPoolError::InvalidAccount.into());
+        }
+        match src[0] {
+            // `deserialize` reads through a cursor and tolerates trailing bytes, unlike
+            // `try_from_slice` (which errors "Not all bytes read" on anything but an exact-size
+            // buffer) — accounts are routinely allocated larger than the packed length (and the
+            // exact length itself varies by `SwapCurve` variant), so this must not require a
+            // precise match.
+            Self::LATEST => Ok(PoolVersion::V1(LiquidityPool::deserialize(&mut &src[1..])?)),
+            _ => Err(LiquidityPoolError::InvalidAccount.into()),
+        }
+    }
+}
+
+// Derives the pool authority PDA for `pool_key`, the same way `initialize_pool` does.
+fn authority_id(program_id: &Pubkey, pool_key: &Pubkey, bump_seed: u8) -> Result<Pubkey, ProgramError> {
+    Pubkey::create_program_address(&[pool_key.as_ref(), &[bump_seed]], program_id)
+        .map_err(|_| LiquidityPoolError::InvalidAccount.into())
+}
+
+// Trade/withdraw fees, modeled on SPL token-swap's `Fees`. The trade fee stays in the pool
+// (it's just left in the reserves, which benefits every LP); the owner fees are minted to
+// `fee_account` as pool tokens so protocol revenue accrues as liquidity.
+// Only `pub` under the `fuzz` feature (see `mod curve` above) — the program itself never
+// needs to construct a `Fees` from outside this crate.
+#[cfg(feature = "fuzz")]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct Fees {
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub owner_trade_fee_numerator: u64,
+    pub owner_trade_fee_denominator: u64,
+    pub owner_withdraw_fee_numerator: u64,
+    pub owner_withdraw_fee_denominator: u64,
+}
+#[cfg(not(feature = "fuzz"))]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+struct Fees {
+    trade_fee_numerator: u64,
+    trade_fee_denominator: u64,
+    owner_trade_fee_numerator: u64,
+    owner_trade_fee_denominator: u64,
+    owner_withdraw_fee_numerator: u64,
+    owner_withdraw_fee_denominator: u64,
+}
+
+impl Fees {
+    pub fn validate(&self) -> Result<(), LiquidityPoolError> {
+        if self.trade_fee_denominator == 0
+            || self.owner_trade_fee_denominator == 0
+            || self.owner_withdraw_fee_denominator == 0
+        {
+            return Err(LiquidityPoolError::InvalidFee);
+        }
+        if self.trade_fee_numerator > self.trade_fee_denominator
+            || self.owner_trade_fee_numerator > self.owner_trade_fee_denominator
+            || self.owner_withdraw_fee_numerator > self.owner_withdraw_fee_denominator
+        {
+            return Err(LiquidityPoolError::InvalidFee);
+        }
+        Ok(())
+    }
+
+    pub fn trading_fee(&self, amount: u128) -> Option<u128> {
+        amount
+            .checked_mul(self.trade_fee_numerator as u128)?
+            .checked_div(self.trade_fee_denominator as u128)
+    }
+
+    pub fn owner_trading_fee(&self, amount: u128) -> Option<u128> {
+        amount
+            .checked_mul(self.owner_trade_fee_numerator as u128)?
+            .checked_div(self.owner_trade_fee_denominator as u128)
+    }
+
+    // Not yet applied anywhere in `remove_liquidty` — part of the `Fees` shape mirroring SPL
+    // token-swap, kept for when owner withdraw fees are enforced.
+    #[allow(dead_code)]
+    pub fn owner_withdraw_fee(&self, amount: u128) -> Option<u128> {
+        amount
+            .checked_mul(self.owner_withdraw_fee_numerator as u128)?
+            .checked_div(self.owner_withdraw_fee_denominator as u128)
+    }
+}
+
 #[derive(Debug)]
 pub enum LiquidityPoolError{
     InvalidAccount,
@@ -31,6 +169,9 @@ pub enum LiquidityPoolError{
     ArithmeticOverflow,
     InvalidTokenPair,
     Unauthorized,
+    InvalidFee,
+    InvalidCurve,
+    SlippageExceeded,
 }
 
 impl From<LiquidityPoolError> for ProgramError {
@@ -40,46 +181,120 @@ impl From<LiquidityPoolError> for ProgramError {
     }
 }
 
+// Only `pub` under the `fuzz` feature (see `mod curve` above) — the program itself never
+// needs to construct a `PoolInstruction` from outside this crate.
+#[cfg(feature = "fuzz")]
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum PoolInstruction {
+    InitializePool { fees: Fees, swap_curve: SwapCurve },
+    AddLiquidity { amount_a: u64, amount_b: u64, minimum_liquidity: u64 },
+    RemoveLiquidity { liquidity_amount: u64, minimum_amount_a: u64, minimum_amount_b: u64 },
+    Swap { amount_in: u64, a_to_b: bool, minimum_amount_out: u64 },
+    /// Deposit only one side of the pair; the rest behaves as if half the deposit were
+    /// swapped into the other token first, so it's charged half the usual trade fee.
+    DepositSingleTokenTypeExactAmountIn { source_amount: u64, minimum_pool_tokens: u64 },
+    /// Inverse of the above: burn the fewest pool tokens that still yield exactly
+    /// `destination_amount` of one side.
+    WithdrawSingleTokenTypeExactAmountOut { destination_amount: u64, maximum_pool_tokens: u64 },
+}
+#[cfg(not(feature = "fuzz"))]
 #[derive(BorshSerialize, BorshDeserialize)]
 enum PoolInstruction {
-    InitializePool,
-    AddLiquidity { amount_a: u64, amount_b: u64 },
-    RemoveLiquidity { liquidity_amount: u64 },
-    Swap { amount_in: u64, a_to_b: bool },
+    InitializePool { fees: Fees, swap_curve: SwapCurve },
+    AddLiquidity { amount_a: u64, amount_b: u64, minimum_liquidity: u64 },
+    RemoveLiquidity { liquidity_amount: u64, minimum_amount_a: u64, minimum_amount_b: u64 },
+    Swap { amount_in: u64, a_to_b: bool, minimum_amount_out: u64 },
+    DepositSingleTokenTypeExactAmountIn { source_amount: u64, minimum_pool_tokens: u64 },
+    WithdrawSingleTokenTypeExactAmountOut { destination_amount: u64, maximum_pool_tokens: u64 },
+}
+
+// pool_tokens minted for depositing `source_amount` of a single side, per SPL token-swap's
+// `deposit_single_token_type_exact_amount_in`: pool_tokens = supply * (sqrt(1 + x) - 1) where
+// x = source_amount_after_half_fee / reserve, computed in u128 to avoid floating point.
+fn single_side_deposit_pool_tokens(source_amount: u128, reserve: u128, liquidity_supply: u128, fees: &Fees) -> Option<u128> {
+    if reserve == 0 || liquidity_supply == 0 {
+        return None; // a single-sided deposit needs an already-seeded pool to price against
+    }
+    let half_trade_fee = fees.trading_fee(source_amount)?.checked_div(2)?;
+    let source_amount_less_fee = source_amount.checked_sub(half_trade_fee)?;
+    let root = reserve.checked_add(source_amount_less_fee)?.checked_mul(reserve)?.sqrt();
+    liquidity_supply.checked_mul(root)?.checked_div(reserve)?.checked_sub(liquidity_supply)
+}
+
+// Values a fee amount already denominated in one side's token against that side's reserve,
+// using the same sqrt single-sided-deposit math as `single_side_deposit_pool_tokens` (so a
+// one-sided fee is valued consistently with a one-sided deposit) but without re-charging a
+// trade fee, since `fee_amount` is already a fee, not a user-supplied deposit. Returns
+// `Some(0)` rather than dividing by zero when there's nothing to value against yet (e.g. a
+// `ConstantProductWithOffset` pool that hasn't received a real deposit on this side).
+fn fee_amount_to_pool_tokens(fee_amount: u128, reserve: u128, liquidity_supply: u128) -> Option<u128> {
+    if fee_amount == 0 || reserve == 0 || liquidity_supply == 0 {
+        return Some(0);
+    }
+    let root = reserve.checked_add(fee_amount)?.checked_mul(reserve)?.sqrt();
+    liquidity_supply.checked_mul(root)?.checked_div(reserve)?.checked_sub(liquidity_supply)
+}
+
+// Inverse of the above: the minimum pool tokens that must be burned to withdraw exactly
+// `destination_amount` of one side, charging the same half trade fee on the withdrawn amount
+// and rounding the burn up so the pool is never left worse off.
+fn single_side_withdraw_pool_tokens(destination_amount: u128, reserve: u128, liquidity_supply: u128, fees: &Fees) -> Option<u128> {
+    if reserve == 0 || liquidity_supply == 0 {
+        return None;
+    }
+    let half_trade_fee = fees.trading_fee(destination_amount)?.checked_div(2)?;
+    let destination_amount_with_fee = destination_amount.checked_add(half_trade_fee)?;
+    if destination_amount_with_fee >= reserve {
+        return None;
+    }
+    let remaining = reserve.checked_sub(destination_amount_with_fee)?;
+    let root_numerator = remaining.checked_mul(reserve)?;
+    let root = root_numerator.sqrt();
+    let scaled = liquidity_supply.checked_mul(root)?;
+    // Flooring the pool tokens left over already rounds the burn up.
+    let pool_tokens_remaining = scaled.checked_div(reserve)?;
+    liquidity_supply.checked_sub(pool_tokens_remaining)
 }
 
 entrypoint!(process_instruction);
 
+// Only `pub` under the `fuzz` feature, so the `fuzz/` harness can drive the real entrypoint
+// (CPIs and all) against an in-memory account model instead of the pool math in isolation.
+#[cfg(feature = "fuzz")]
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    process_instruction_impl(program_id, accounts, instruction_data)
+}
+#[cfg(not(feature = "fuzz"))]
 fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-   let accounts_iter = &mut accounts.iter();
-
-   let pool_state = next_account_info(accounts_iter)?;
-   let authority = next_account_info(accounts_iter)?;
-   let token_a_mint = next_account_info(accounts_iter)?;
-   let token_b_mint = next_account_info(accounts_iter)?;
-   let token_a_vault = next_account_info(accounts_iter)?;
-   let token_b_vault = next_account_info(accounts_iter);
-   let liquidity_supply = next_account_info(accounts_iter)?;
-   let token_a_reserve = next_account_info(accounts_iter)?;
-   let token_b_reserve = next_account_info(accounts_iter)?;
+    process_instruction_impl(program_id, accounts, instruction_data)
+}
 
+fn process_instruction_impl(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+   // Each handler below re-parses `accounts` itself (its own account ordering differs
+   // instruction to instruction), so nothing is parsed up here before dispatch.
    let instruction = PoolInstruction::try_from_slice(instruction_data)?;
 
    match instruction {
-    PoolInstruction::InitializePool => initialize_pool(program_id, accounts),
-    PoolInstruction::AddLiquidity { amount_a, amount_b } => {
-        add_liquidity(program_id, accounts, amount_a, amount_b)
+    PoolInstruction::InitializePool { fees, swap_curve } => initialize_pool(program_id, accounts, fees, swap_curve),
+    PoolInstruction::AddLiquidity { amount_a, amount_b, minimum_liquidity } => {
+        add_liquidity(program_id, accounts, amount_a, amount_b, minimum_liquidity)
     },
-    PoolInstruction::RemoveLiquidity { liquidity_amount } => {
-        remove_liquidty(program_id, accounts, liquidity_amount)
+    PoolInstruction::RemoveLiquidity { liquidity_amount, minimum_amount_a, minimum_amount_b } => {
+        remove_liquidty(program_id, accounts, liquidity_amount, minimum_amount_a, minimum_amount_b)
     },
-    PoolInstruction::Swap { amount_in, a_to_b } => {
-        swap(program_id, accounts, amount_in, a_to_b)
+    PoolInstruction::Swap { amount_in, a_to_b, minimum_amount_out } => {
+        swap(program_id, accounts, amount_in, a_to_b, minimum_amount_out)
+    }
+    PoolInstruction::DepositSingleTokenTypeExactAmountIn { source_amount, minimum_pool_tokens } => {
+        deposit_single_token_type_exact_amount_in(program_id, accounts, source_amount, minimum_pool_tokens)
+    }
+    PoolInstruction::WithdrawSingleTokenTypeExactAmountOut { destination_amount, maximum_pool_tokens } => {
+        withdraw_single_token_type_exact_amount_out(program_id, accounts, destination_amount, maximum_pool_tokens)
     }
     }?;
 
 
-   fn initialize_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+   fn initialize_pool(program_id: &Pubkey, accounts: &[AccountInfo], fees: Fees, swap_curve: SwapCurve) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
     // Extract accounts
@@ -90,8 +305,12 @@ fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instructio
     let token_a_vault = next_account_info(accounts_iter)?;
     let token_b_vault = next_account_info(accounts_iter)?;
     let liquidity_mint = next_account_info(accounts_iter)?;
+    let fee_account = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
 
+    fees.validate()?;
+    swap_curve.validate()?;
+
     // --- Validation ---
     // 1. Pool state account: Must be writable and owned by the program
     if !pool_state.is_writable {
@@ -128,32 +347,73 @@ fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instructio
     }
 
     // --- Check if pool state is uninitialized ---
-    if !pool_state.data_is_empty() {
-        return Err(LiquidityPoolError::AlreadyInitialized.into()); // Ensures pool is fresh
+    // A freshly allocated account reads back as all zeroes, which isn't a valid version byte,
+    // so `unpack` erroring out here just means "nothing written yet" - only an already
+    // initialized pool should make us bail.
+    if let Ok(PoolVersion::V1(existing)) = PoolVersion::unpack(&pool_state.data.borrow()) {
+        if existing.is_initialized {
+            return Err(LiquidityPoolError::AlreadyInitialized.into());
+        }
+    }
+
+    // --- Derive the pool authority PDA and make sure the caller passed the real one ---
+    // Mirrors SPL token-swap: the authority is `find_program_address(&[pool_state], program_id)`,
+    // and the caller must have already pointed the vaults/mint at it before calling us.
+    let (authority_pubkey, bump_seed) = Pubkey::find_program_address(&[pool_state.key.as_ref()], program_id);
+    if *authority.key != authority_pubkey {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+
+    let token_a_vault_account = spl_token::state::Account::unpack(&token_a_vault.data.borrow())?;
+    if token_a_vault_account.owner != authority_pubkey {
+        return Err(LiquidityPoolError::InvalidAccount.into()); // Vault must already be owned by the PDA
+    }
+    let token_b_vault_account = spl_token::state::Account::unpack(&token_b_vault.data.borrow())?;
+    if token_b_vault_account.owner != authority_pubkey {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    let liquidity_mint_account = spl_token::state::Mint::unpack(&liquidity_mint.data.borrow())?;
+    if liquidity_mint_account.mint_authority != COption::Some(authority_pubkey) {
+        return Err(LiquidityPoolError::InvalidAccount.into()); // Mint authority must already be the PDA
+    }
+
+    // 6. Fee account: must be an SPL token account for the pool's own liquidity mint
+    if *fee_account.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    let fee_account_data = spl_token::state::Account::unpack(&fee_account.data.borrow())?;
+    if fee_account_data.mint != *liquidity_mint.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
     }
 
     // --- Set initial pool state ---
     let pool = LiquidityPool {
-        authority: *authority.key,
+        is_initialized: true,
+        authority: authority_pubkey,
+        bump_seed,
         token_a_mint: *token_a_mint.key,
         token_b_mint: *token_b_mint.key,
         token_a_vault: *token_a_vault.key,
         token_b_vault: *token_b_vault.key,
-        liquidity_mint: *liquidity_mint.key, 
+        liquidity_mint: *liquidity_mint.key,
+        fee_account: *fee_account.key,
+        fees,
+        swap_curve,
         liquidity_supply: 0,
         token_a_reserve: 0,
         token_b_reserve: 0,
     };
-    pool.serialize(&mut *pool_state.data.borrow_mut())?;
+    PoolVersion::V1(pool).pack(&mut pool_state.data.borrow_mut())?;
 
 
     Ok(())
 }
 
-fn add_liquidity(program_id: &Pubkey, accounts: &[AccountInfo], amount_a: u64, amount_b: u64) -> ProgramResult{
+fn add_liquidity(program_id: &Pubkey, accounts: &[AccountInfo], amount_a: u64, amount_b: u64, minimum_liquidity: u64) -> ProgramResult{
     let accounts_iter = &mut accounts.iter();
 
     let pool_state = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
     let user_token_a = next_account_info(accounts_iter)?;
     let user_token_b = next_account_info(accounts_iter)?;
     let token_a_vault = next_account_info(accounts_iter)?;
@@ -194,7 +454,15 @@ fn add_liquidity(program_id: &Pubkey, accounts: &[AccountInfo], amount_a: u64, a
         return Err(LiquidityPoolError::Unauthorized.into());
     }
 
-    let mut pool = LiquidityPool::try_from_slice(&pool_state.data.borrow())?;
+    let PoolVersion::V1(mut pool) = PoolVersion::unpack(&pool_state.data.borrow())?;
+
+    if !pool.is_initialized {
+        return Err(LiquidityPoolError::NotInitialized.into());
+    }
+
+    if *authority.key != authority_id(program_id, pool_state.key, pool.bump_seed)? {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
 
     if pool.token_a_vault != *token_a_vault.key || pool.token_b_vault != *token_b_vault.key {
         return Err(LiquidityPoolError::InvalidAccount.into());
@@ -210,18 +478,20 @@ fn add_liquidity(program_id: &Pubkey, accounts: &[AccountInfo], amount_a: u64, a
         return Err(LiquidityPoolError::InvalidAmount.into())
     }
 
-    let liquidity_to_mint = if pool.liquidity_supply == 0 {
-        ((amount_a as u128) * (amount_b as u128)).sqrt() as u128
-    } else {
-        let liquidity_a = (amount_a as u128 * pool.liquidity_supply as u128) / pool.token_a_reserve as u128;
-        let liquidity_b = (amount_b as u128 * pool.liquidity_supply as u128 ) / pool.token_b_reserve as u128;
-
-        liquidity_a.min(liquidity_b) as u128
-    };
+    let liquidity_to_mint = pool.swap_curve.deposit_liquidity_tokens(
+        amount_a as u128,
+        amount_b as u128,
+        pool.token_a_reserve as u128,
+        pool.token_b_reserve as u128,
+        pool.liquidity_supply as u128,
+    ).ok_or(ProgramError::ArithmeticOverflow)?;
 
     if liquidity_to_mint == 0 {
         return Err(LiquidityPoolError::InvalidAmount.into())
     }
+    if liquidity_to_mint < minimum_liquidity as u128 {
+        return Err(LiquidityPoolError::SlippageExceeded.into());
+    }
 
 
     // transfer token a
@@ -250,15 +520,16 @@ fn add_liquidity(program_id: &Pubkey, accounts: &[AccountInfo], amount_a: u64, a
         token_program.clone()
     ])?;
 
-    //mint liquidity token
+    //mint liquidity token - the pool authority PDA is the mint authority, so this has to be signed
 
-    invoke(&token_instruction::mint_to(token_program.key, liquidity_mint.key, user_liquidity.key, &pool.authority, &[], liquidity_to_mint as u64,)?,
+    invoke_signed(&token_instruction::mint_to(token_program.key, liquidity_mint.key, user_liquidity.key, &pool.authority, &[], liquidity_to_mint as u64,)?,
      &[
         liquidity_mint.clone(),
         user_liquidity.clone(),
-        pool_state.clone(),
+        authority.clone(),
         token_program.clone(),
-     ]
+     ],
+     &[&[pool_state.key.as_ref(), &[pool.bump_seed]]],
     )?;
 
 // updating pool reserve state
@@ -266,17 +537,18 @@ fn add_liquidity(program_id: &Pubkey, accounts: &[AccountInfo], amount_a: u64, a
 pool.token_a_reserve = pool.token_a_reserve.checked_add(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
 pool.token_b_reserve = pool.token_b_reserve.checked_add(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
 pool.liquidity_supply = pool.liquidity_supply.checked_add(liquidity_to_mint as u64).ok_or(ProgramError::ArithmeticOverflow)?;
-pool.serialize(&mut pool_state.data.borrow_mut().as_mut())?;
-    
+PoolVersion::V1(pool).pack(&mut pool_state.data.borrow_mut())?;
+
 
 
     Ok(())
 }
 
-fn remove_liquidty(program_id: &Pubkey, accounts: &[AccountInfo], liquidity_amount: u64) -> ProgramResult {
+fn remove_liquidty(program_id: &Pubkey, accounts: &[AccountInfo], liquidity_amount: u64, minimum_amount_a: u64, minimum_amount_b: u64) -> ProgramResult {
     let account_iter = &mut accounts.iter();
 
     let pool_state = next_account_info(account_iter)?;
+    let authority = next_account_info(account_iter)?;
     let user_liquidity = next_account_info(account_iter)?;
     let token_a_vault = next_account_info(account_iter)?;
     let token_b_vault = next_account_info(account_iter)?;
@@ -316,18 +588,35 @@ fn remove_liquidty(program_id: &Pubkey, accounts: &[AccountInfo], liquidity_amou
         return Err(LiquidityPoolError::Unauthorized.into());
     }
 
-    let mut pool = LiquidityPool::try_from_slice(&pool_state.data.borrow())?;
+    let PoolVersion::V1(mut pool) = PoolVersion::unpack(&pool_state.data.borrow())?;
+
+    if !pool.is_initialized {
+        return Err(LiquidityPoolError::NotInitialized.into());
+    }
+
+    if *authority.key != authority_id(program_id, pool_state.key, pool.bump_seed)? {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
 
     if liquidity_amount == 0 || liquidity_amount > pool.liquidity_supply {
         return  Err(LiquidityPoolError::InvalidAmount.into());
     }
 
-    let  amount_a = (liquidity_amount as u128 * pool.token_a_reserve as u128 / pool.liquidity_supply as u128) as u64;
-    let  amount_b = (liquidity_amount as u128 * pool.token_b_reserve as u128 / pool.liquidity_supply as u128) as u64;
+    let (amount_a, amount_b) = pool.swap_curve.withdraw_trading_tokens(
+        liquidity_amount as u128,
+        pool.liquidity_supply as u128,
+        pool.token_a_reserve as u128,
+        pool.token_b_reserve as u128,
+    ).ok_or(ProgramError::ArithmeticOverflow)?;
+    let amount_a = amount_a as u64;
+    let amount_b = amount_b as u64;
 
     if amount_a == 0 || amount_b == 0 {
         return Err(LiquidityPoolError::InvalidAmount.into());
     }
+    if amount_a < minimum_amount_a || amount_b < minimum_amount_b {
+        return Err(LiquidityPoolError::SlippageExceeded.into());
+    }
 
     // burn liquidity
     invoke(&token_instruction::burn(token_program.key, user_liquidity.key, liquidity_mint.key, user.key, &[], liquidity_amount)?, &[
@@ -337,6 +626,8 @@ fn remove_liquidty(program_id: &Pubkey, accounts: &[AccountInfo], liquidity_amou
         token_program.clone(),
     ])?;
 
+    let signer_seeds: &[&[u8]] = &[pool_state.key.as_ref(), &[pool.bump_seed]];
+
     invoke_signed(
         &token_instruction::transfer(
             token_program.key,
@@ -349,12 +640,12 @@ fn remove_liquidty(program_id: &Pubkey, accounts: &[AccountInfo], liquidity_amou
         &[
             token_a_vault.clone(),
             user_token_a.clone(),
-            pool_state.clone(),
+            authority.clone(),
             token_program.clone(),
         ],
-        &[&[/* PDA seeds for authority */]],
+        &[signer_seeds],
     )?;
-    
+
     invoke_signed(
         &token_instruction::transfer(
             token_program.key,
@@ -367,37 +658,40 @@ fn remove_liquidty(program_id: &Pubkey, accounts: &[AccountInfo], liquidity_amou
         &[
             token_b_vault.clone(),
             user_token_b.clone(),
-            pool_state.clone(),
+            authority.clone(),
             token_program.clone(),
         ],
-        &[&[/* PDA seeds for authority */]],
+        &[signer_seeds],
     )?;
 
     // Continuing in remove_liquidity
     pool.token_a_reserve = pool.token_a_reserve.checked_sub(amount_a).ok_or(ProgramError::ArithmeticOverflow)?;
     pool.token_b_reserve = pool.token_b_reserve.checked_sub(amount_b).ok_or(ProgramError::ArithmeticOverflow)?;
     pool.liquidity_supply = pool.liquidity_supply.checked_sub(liquidity_amount).ok_or(ProgramError::ArithmeticOverflow)?;
-    pool.serialize(&mut *pool_state.data.borrow_mut())?;
+    PoolVersion::V1(pool).pack(&mut pool_state.data.borrow_mut())?;
 
 
     Ok(())
 }
 
-fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: bool) -> ProgramResult {
+fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: bool, minimum_amount_out: u64) -> ProgramResult {
 
     let account_iter =&mut accounts.iter();
 
     let pool_state = next_account_info(account_iter)?;
+    let authority = next_account_info(account_iter)?;
     let user_input_token = next_account_info(account_iter)?;
     let user_output_token = next_account_info(account_iter)?;
     let input_vault = next_account_info(account_iter)?;
     let output_vault = next_account_info(account_iter)?;
+    let liquidity_mint = next_account_info(account_iter)?;
+    let fee_account = next_account_info(account_iter)?;
     let token_program = next_account_info(account_iter)?;
     let user = next_account_info(account_iter)?;
 
     if !pool_state.is_writable || *pool_state.owner != *program_id {
         return Err(LiquidityPoolError::InvalidAccount.into())
-    } 
+    }
 
     if !user_input_token.is_writable || *user_input_token.owner != *token_program.key{
         return Err(LiquidityPoolError::InvalidAccount.into())
@@ -415,6 +709,14 @@ fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: b
         return Err(LiquidityPoolError::InvalidAccount.into())
     }
 
+    if !liquidity_mint.is_writable || *liquidity_mint.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into())
+    }
+
+    if !fee_account.is_writable || *fee_account.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into())
+    }
+
     if *token_program.key != spl_token::ID{
         return Err(LiquidityPoolError::InvalidAccount.into());
     }
@@ -424,7 +726,19 @@ fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: b
     }
 
     // validating the pool state
-    let mut pool = LiquidityPool::try_from_slice(*pool_state.data.borrow())?;
+    let PoolVersion::V1(mut pool) = PoolVersion::unpack(*pool_state.data.borrow())?;
+
+    if !pool.is_initialized {
+        return Err(LiquidityPoolError::NotInitialized.into());
+    }
+
+    if *authority.key != authority_id(program_id, pool_state.key, pool.bump_seed)? {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+
+    if pool.liquidity_mint != *liquidity_mint.key || pool.fee_account != *fee_account.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
 
     if a_to_b {
         if  pool.token_a_vault != *input_vault.key || pool.token_b_vault != *output_vault.key {
@@ -437,7 +751,7 @@ fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: b
     } else {
         if pool.token_b_vault != *input_vault.key || pool.token_a_vault != *output_vault.key {
             return Err(LiquidityPoolError::InvalidAccount.into());
-        } 
+        }
 
         if pool.token_b_mint != *user_input_token.key || pool.token_a_mint != *user_output_token.key{
             return Err(LiquidityPoolError::InvalidAccount.into());
@@ -448,45 +762,53 @@ fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: b
         return Err(LiquidityPoolError::InvalidAmount.into());
     }
 
-    let fee_enumerator = 30;
-    let fee_denumerator = 10000;
-
-    let (input_reserve, output_reserve) = if a_to_b {
-        (pool.token_a_reserve, pool.token_b_reserve)  
-    } else {
-        (pool.token_b_reserve, pool.token_a_reserve)
-    };
-
-    let amount_in_after_fee = amount_in.checked_mul(fee_denumerator - fee_enumerator)
-    .ok_or(ProgramError::ArithmeticOverflow)?
-    .checked_div(fee_denumerator)
-    .ok_or(ProgramError::ArithmeticOverflow)?;
+    let input_reserve = if a_to_b { pool.token_a_reserve } else { pool.token_b_reserve };
+    let trade_direction = if a_to_b { TradeDirection::AtoB } else { TradeDirection::BtoA };
 
-    let invariant = (input_reserve as u128) * (output_reserve as u128);
+    // The trade fee is left in `amount_in` (it lands in the reserve below, which benefits
+    // every LP); the owner fee is carved out and minted to `fee_account` as pool tokens instead.
+    let trade_fee = pool.fees.trading_fee(amount_in as u128).ok_or(ProgramError::ArithmeticOverflow)?;
+    let owner_fee = pool.fees.owner_trading_fee(amount_in as u128).ok_or(ProgramError::ArithmeticOverflow)?;
 
-    let new_input_reserve = (input_reserve as u128) * (amount_in_after_fee as u128);
+    let amount_in_after_fees = (amount_in as u128)
+        .checked_sub(trade_fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_sub(owner_fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
 
-    let amount_out= output_reserve.checked_sub((invariant / new_input_reserve)
-    .try_into()
-    .map_err(|_| ProgramError::ArithmeticOverflow)?, )
-    .ok_or(ProgramError::ArithmeticOverflow)?; 
+    let swap_result = pool.swap_curve.swap(
+        amount_in_after_fees,
+        pool.token_a_reserve as u128,
+        pool.token_b_reserve as u128,
+        trade_direction,
+    ).ok_or(ProgramError::ArithmeticOverflow)?;
+    let amount_out = swap_result.amount_out;
 
     if amount_out == 0 {
         return Err(LiquidityPoolError::InvalidAmount.into())
     }
-    
-    // Transfer Input token
-    let _ = invoke_signed(&token_instruction::transfer(
-        token_program.key, 
-        user_input_token.key, 
-        input_vault.key, 
-        user.key, 
-        &[], 
+    if amount_out < minimum_amount_out {
+        return Err(LiquidityPoolError::SlippageExceeded.into());
+    }
+
+    // Mint the owner fee's pool-token value to `fee_account`, valued against the reserve
+    // before this swap's input lands in it.
+    let owner_fee_pool_tokens: u64 = fee_amount_to_pool_tokens(owner_fee, input_reserve as u128, pool.liquidity_supply as u128)
+        .and_then(|v| u64::try_from(v).ok())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // Transfer input token - the user owns this account, so a plain `invoke` signed by `user` is correct
+    invoke(&token_instruction::transfer(
+        token_program.key,
+        user_input_token.key,
+        input_vault.key,
+        user.key,
+        &[],
          amount_in)?,
-     &[user_input_token.clone(), input_vault.clone(), user.clone(), token_program.clone()],  
-     &[&[/* PDA seeds for authority */]],);
+     &[user_input_token.clone(), input_vault.clone(), user.clone(), token_program.clone()],
+    )?;
 
-    //  transfer output token
+    //  transfer output token - the vault is owned by the pool authority PDA, so this must be signed
     invoke_signed(
         &token_instruction::transfer(
             token_program.key,
@@ -499,12 +821,33 @@ fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: b
         &[
             output_vault.clone(),
             user_output_token.clone(),
-            pool_state.clone(),
+            authority.clone(),
             token_program.clone(),
         ],
-        &[&[/* PDA seeds for authority */]],
+        &[&[pool_state.key.as_ref(), &[pool.bump_seed]]],
     )?;
 
+    if owner_fee_pool_tokens > 0 {
+        invoke_signed(
+            &token_instruction::mint_to(
+                token_program.key,
+                liquidity_mint.key,
+                fee_account.key,
+                &pool.authority,
+                &[],
+                owner_fee_pool_tokens,
+            )?,
+            &[
+                liquidity_mint.clone(),
+                fee_account.clone(),
+                authority.clone(),
+                token_program.clone(),
+            ],
+            &[&[pool_state.key.as_ref(), &[pool.bump_seed]]],
+        )?;
+        pool.liquidity_supply = pool.liquidity_supply.checked_add(owner_fee_pool_tokens).ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+
    if a_to_b {
     pool.token_a_reserve = pool.token_a_reserve.checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
     pool.token_b_reserve = pool.token_b_reserve.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
@@ -512,11 +855,237 @@ fn swap(program_id: &Pubkey, accounts: &[AccountInfo], amount_in: u64, a_to_b: b
        pool.token_b_reserve = pool.token_b_reserve.checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
        pool.token_a_reserve = pool.token_a_reserve.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
    }
-    pool.serialize(&mut *pool_state.data.borrow_mut())?;
-    
+    PoolVersion::V1(pool).pack(&mut pool_state.data.borrow_mut())?;
+
 
 Ok(())
 }
-   
+
+fn deposit_single_token_type_exact_amount_in(program_id: &Pubkey, accounts: &[AccountInfo], source_amount: u64, minimum_pool_tokens: u64) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let pool_state = next_account_info(account_iter)?;
+    let authority = next_account_info(account_iter)?;
+    let user_source_token = next_account_info(account_iter)?;
+    let source_vault = next_account_info(account_iter)?;
+    let liquidity_mint = next_account_info(account_iter)?;
+    let user_liquidity = next_account_info(account_iter)?;
+    let token_program = next_account_info(account_iter)?;
+    let user = next_account_info(account_iter)?;
+
+    if !pool_state.is_writable || *pool_state.owner != *program_id {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !user_source_token.is_writable || *user_source_token.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !source_vault.is_writable || *source_vault.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !liquidity_mint.is_writable || *liquidity_mint.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !user_liquidity.is_writable || *user_liquidity.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if *token_program.key != spl_token::id() {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !user.is_signer {
+        return Err(LiquidityPoolError::Unauthorized.into());
+    }
+
+    let PoolVersion::V1(mut pool) = PoolVersion::unpack(&pool_state.data.borrow())?;
+
+    if !pool.is_initialized {
+        return Err(LiquidityPoolError::NotInitialized.into());
+    }
+
+    if *authority.key != authority_id(program_id, pool_state.key, pool.bump_seed)? {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if pool.liquidity_mint != *liquidity_mint.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+
+    let source_is_a = if pool.token_a_vault == *source_vault.key {
+        true
+    } else if pool.token_b_vault == *source_vault.key {
+        false
+    } else {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    };
+
+    let user_source_mint = spl_token::state::Account::unpack(&user_source_token.data.borrow())?.mint;
+    let expected_mint = if source_is_a { pool.token_a_mint } else { pool.token_b_mint };
+    if user_source_mint != expected_mint {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+
+    if source_amount == 0 {
+        return Err(LiquidityPoolError::InvalidAmount.into());
+    }
+
+    let reserve = if source_is_a { pool.token_a_reserve } else { pool.token_b_reserve };
+    let pool_tokens = single_side_deposit_pool_tokens(source_amount as u128, reserve as u128, pool.liquidity_supply as u128, &pool.fees)
+        .and_then(|amount| u64::try_from(amount).ok())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    if pool_tokens == 0 || pool_tokens < minimum_pool_tokens {
+        return Err(LiquidityPoolError::SlippageExceeded.into());
+    }
+
+    invoke(&token_instruction::transfer(
+        token_program.key,
+        user_source_token.key,
+        source_vault.key,
+        user.key,
+        &[],
+        source_amount,
+    )?, &[
+        user_source_token.clone(),
+        source_vault.clone(),
+        user.clone(),
+        token_program.clone(),
+    ])?;
+
+    invoke_signed(&token_instruction::mint_to(
+        token_program.key,
+        liquidity_mint.key,
+        user_liquidity.key,
+        &pool.authority,
+        &[],
+        pool_tokens,
+    )?, &[
+        liquidity_mint.clone(),
+        user_liquidity.clone(),
+        authority.clone(),
+        token_program.clone(),
+    ], &[&[pool_state.key.as_ref(), &[pool.bump_seed]]])?;
+
+    if source_is_a {
+        pool.token_a_reserve = pool.token_a_reserve.checked_add(source_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    } else {
+        pool.token_b_reserve = pool.token_b_reserve.checked_add(source_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+    pool.liquidity_supply = pool.liquidity_supply.checked_add(pool_tokens).ok_or(ProgramError::ArithmeticOverflow)?;
+    PoolVersion::V1(pool).pack(&mut pool_state.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn withdraw_single_token_type_exact_amount_out(program_id: &Pubkey, accounts: &[AccountInfo], destination_amount: u64, maximum_pool_tokens: u64) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let pool_state = next_account_info(account_iter)?;
+    let authority = next_account_info(account_iter)?;
+    let user_liquidity = next_account_info(account_iter)?;
+    let liquidity_mint = next_account_info(account_iter)?;
+    let destination_vault = next_account_info(account_iter)?;
+    let user_destination_token = next_account_info(account_iter)?;
+    let user = next_account_info(account_iter)?;
+    let token_program = next_account_info(account_iter)?;
+
+    if !pool_state.is_writable || *pool_state.owner != *program_id {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !user_liquidity.is_writable || *user_liquidity.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !liquidity_mint.is_writable || *liquidity_mint.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !destination_vault.is_writable || *destination_vault.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !user_destination_token.is_writable || *user_destination_token.owner != *token_program.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if *token_program.key != spl_token::id() {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if !user.is_signer {
+        return Err(LiquidityPoolError::Unauthorized.into());
+    }
+
+    let PoolVersion::V1(mut pool) = PoolVersion::unpack(&pool_state.data.borrow())?;
+
+    if !pool.is_initialized {
+        return Err(LiquidityPoolError::NotInitialized.into());
+    }
+
+    if *authority.key != authority_id(program_id, pool_state.key, pool.bump_seed)? {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+    if pool.liquidity_mint != *liquidity_mint.key {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+
+    let destination_is_a = if pool.token_a_vault == *destination_vault.key {
+        true
+    } else if pool.token_b_vault == *destination_vault.key {
+        false
+    } else {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    };
+
+    let user_destination_mint = spl_token::state::Account::unpack(&user_destination_token.data.borrow())?.mint;
+    let expected_mint = if destination_is_a { pool.token_a_mint } else { pool.token_b_mint };
+    if user_destination_mint != expected_mint {
+        return Err(LiquidityPoolError::InvalidAccount.into());
+    }
+
+    if destination_amount == 0 {
+        return Err(LiquidityPoolError::InvalidAmount.into());
+    }
+
+    let reserve = if destination_is_a { pool.token_a_reserve } else { pool.token_b_reserve };
+    let pool_tokens = single_side_withdraw_pool_tokens(destination_amount as u128, reserve as u128, pool.liquidity_supply as u128, &pool.fees)
+        .and_then(|amount| u64::try_from(amount).ok())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    if pool_tokens == 0 || pool_tokens > maximum_pool_tokens || pool_tokens > pool.liquidity_supply {
+        return Err(LiquidityPoolError::SlippageExceeded.into());
+    }
+
+    invoke(&token_instruction::burn(
+        token_program.key,
+        user_liquidity.key,
+        liquidity_mint.key,
+        user.key,
+        &[],
+        pool_tokens,
+    )?, &[
+        user_liquidity.clone(),
+        liquidity_mint.clone(),
+        user.clone(),
+        token_program.clone(),
+    ])?;
+
+    invoke_signed(&token_instruction::transfer(
+        token_program.key,
+        destination_vault.key,
+        user_destination_token.key,
+        &pool.authority,
+        &[],
+        destination_amount,
+    )?, &[
+        destination_vault.clone(),
+        user_destination_token.clone(),
+        authority.clone(),
+        token_program.clone(),
+    ], &[&[pool_state.key.as_ref(), &[pool.bump_seed]]])?;
+
+    if destination_is_a {
+        pool.token_a_reserve = pool.token_a_reserve.checked_sub(destination_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    } else {
+        pool.token_b_reserve = pool.token_b_reserve.checked_sub(destination_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+    pool.liquidity_supply = pool.liquidity_supply.checked_sub(pool_tokens).ok_or(ProgramError::ArithmeticOverflow)?;
+    PoolVersion::V1(pool).pack(&mut pool_state.data.borrow_mut())?;
+
+    Ok(())
+}
+
 Ok(())
 }